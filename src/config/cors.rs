@@ -1,8 +1,69 @@
 use anyhow::{Error, Result};
+use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use regex::Regex;
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::time::Duration;
 
+/// The set of origins a [`CorsConfig`] allows, configured via
+/// `CorsConfigBuilder::allow_origin`, `allow_origins`, or
+/// `allow_origin_patterns`.
+#[derive(Clone, Debug)]
+pub enum AllowedOrigins {
+    /// Allow every origin. Emits a literal `*` in
+    /// `Access-Control-Allow-Origin`.
+    Any,
+    /// Allow only the exact origins in this set. The matching origin is
+    /// echoed back in `Access-Control-Allow-Origin` rather than `*`, which
+    /// is required whenever credentials are involved.
+    Exact(HashSet<String>),
+    /// Allow any origin matching one of these compiled patterns (e.g. any
+    /// subdomain of a given host). The matching origin is echoed back in
+    /// `Access-Control-Allow-Origin` rather than `*`.
+    Patterns(Vec<Regex>),
+    /// Allow any origin by reflecting the request's `Origin` header back
+    /// verbatim, rather than emitting `*`. Used by
+    /// `CorsConfig::very_permissive` so that credentialed requests keep
+    /// working, which a literal `*` cannot support.
+    Reflect,
+}
+
+impl AllowedOrigins {
+    /// Returns the value to emit in `Access-Control-Allow-Origin` for the
+    /// given request `Origin` header value, or `None` if the origin is not
+    /// allowed (in which case no CORS headers should be sent at all).
+    pub fn matching_origin(&self, request_origin: &str) -> Option<String> {
+        match self {
+            AllowedOrigins::Any => Some(String::from("*")),
+            AllowedOrigins::Exact(origins) => origins
+                .contains(request_origin)
+                .then(|| request_origin.to_string()),
+            AllowedOrigins::Patterns(patterns) => patterns
+                .iter()
+                .any(|pattern| pattern.is_match(request_origin))
+                .then(|| request_origin.to_string()),
+            AllowedOrigins::Reflect => Some(request_origin.to_string()),
+        }
+    }
+}
+
+impl PartialEq for AllowedOrigins {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (AllowedOrigins::Any, AllowedOrigins::Any) => true,
+            (AllowedOrigins::Exact(a), AllowedOrigins::Exact(b)) => a == b,
+            (AllowedOrigins::Patterns(a), AllowedOrigins::Patterns(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.as_str() == y.as_str())
+            }
+            (AllowedOrigins::Reflect, AllowedOrigins::Reflect) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for AllowedOrigins {}
+
 /// CORS (Cross Origin Resource Sharing) configuration for the HTTP/S
 /// server.
 ///
@@ -46,7 +107,7 @@ pub struct CorsConfig {
     /// response can be shared with requesting code from the given origin.
     ///
     /// Source: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Access-Control-Allow-Origin
-    allow_origin: Option<String>,
+    allow_origin: Option<AllowedOrigins>,
     /// The Access-Control-Expose-Headers response header allows a server to
     /// indicate which response headers should be made available to scripts
     /// running in the browser, in response to a cross-origin request.
@@ -78,18 +139,37 @@ pub struct CorsConfig {
     /// preflight request is always an OPTIONS and doesn't use the same method as
     /// the actual request.
     request_method: Option<String>,
+    /// When `true`, the CORS layer reflects the request's
+    /// `Access-Control-Request-Method` and `Access-Control-Request-Headers`
+    /// values back in the response instead of using the configured
+    /// `allow_methods` / `allow_headers` lists. Set via
+    /// `CorsConfig::very_permissive`.
+    very_permissive: bool,
+    /// Extra `Vary` header values to merge with the ones the CORS layer
+    /// derives automatically (e.g. `Origin` when reflecting origins). Lets
+    /// deployments behind a CDN add their own cache-key dimensions without
+    /// overwriting the ones CORS correctness depends on.
+    vary: Option<Vec<String>>,
 }
 
 impl CorsConfig {
     pub fn builder() -> CorsConfigBuilder {
         CorsConfigBuilder {
             config: CorsConfig::default(),
+            origin_patterns: None,
         }
     }
 
+    /// Returns the value to emit in `Access-Control-Allow-Origin` for the
+    /// given request `Origin` header value, or `None` if the origin is not
+    /// allowed (in which case no CORS headers should be sent at all).
+    pub fn resolve_allow_origin(&self, request_origin: &str) -> Option<String> {
+        self.allow_origin.as_ref()?.matching_origin(request_origin)
+    }
+
     pub fn allow_all() -> Self {
         CorsConfig {
-            allow_origin: Some(String::from("*")),
+            allow_origin: Some(AllowedOrigins::Any),
             allow_methods: Some(vec![
                 "GET".to_string(),
                 "POST".to_string(),
@@ -108,6 +188,31 @@ impl CorsConfig {
             expose_headers: None,
             request_headers: None,
             request_method: None,
+            very_permissive: false,
+            vary: None,
+        }
+    }
+
+    /// A drop-in permissive setup for local development: reflects the
+    /// request's `Origin`, `Access-Control-Request-Method`, and
+    /// `Access-Control-Request-Headers` values back in the response while
+    /// keeping `Access-Control-Allow-Credentials: true`.
+    ///
+    /// Unlike `allow_all()`, which emits a literal `*` and therefore cannot
+    /// be combined with credentials, `very_permissive()` reflects the
+    /// incoming request's origin, so credentialed fetches keep working.
+    pub fn very_permissive() -> Self {
+        CorsConfig {
+            allow_origin: Some(AllowedOrigins::Reflect),
+            allow_methods: None,
+            allow_headers: None,
+            allow_credentials: true,
+            max_age: Some(Duration::from_secs(43200)),
+            expose_headers: None,
+            request_headers: None,
+            request_method: None,
+            very_permissive: true,
+            vary: None,
         }
     }
 }
@@ -123,18 +228,81 @@ impl Default for CorsConfig {
             expose_headers: None,
             request_headers: None,
             request_method: None,
+            very_permissive: false,
+            vary: None,
         }
     }
 }
 
+/// Errors returned by `CorsConfigBuilder::build` when the configured
+/// combination of options violates the CORS specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CorsConfigError {
+    /// `allow_credentials` was combined with a wildcard `Access-Control-Allow-Origin`.
+    /// Browsers always ignore `Access-Control-Allow-Credentials` when the origin is
+    /// `*`, so this combination can never work as intended.
+    CredentialsWithWildcardOrigin,
+    /// `allow_credentials` was combined with a wildcard (`*`) `Access-Control-Allow-Headers`.
+    CredentialsWithWildcardHeaders,
+    /// `allow_credentials` was combined with a wildcard (`*`) `Access-Control-Allow-Methods`.
+    CredentialsWithWildcardMethods,
+    /// An `allow_origin_patterns` regex failed to compile.
+    InvalidOriginPattern(String),
+}
+
+impl std::fmt::Display for CorsConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CorsConfigError::CredentialsWithWildcardOrigin => write!(
+                f,
+                "allow_credentials cannot be combined with a wildcard (`*`) allow_origin"
+            ),
+            CorsConfigError::CredentialsWithWildcardHeaders => write!(
+                f,
+                "allow_credentials cannot be combined with a wildcard (`*`) allow_headers"
+            ),
+            CorsConfigError::CredentialsWithWildcardMethods => write!(
+                f,
+                "allow_credentials cannot be combined with a wildcard (`*`) allow_methods"
+            ),
+            CorsConfigError::InvalidOriginPattern(pattern) => {
+                write!(f, "invalid allow_origin_patterns regex: {pattern}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CorsConfigError {}
+
 /// CorsConfig Builder
 pub struct CorsConfigBuilder {
     config: CorsConfig,
+    origin_patterns: Option<Vec<String>>,
 }
 
 impl CorsConfigBuilder {
     pub fn allow_origin(mut self, origin: String) -> Self {
-        self.config.allow_origin = Some(origin);
+        self.config.allow_origin = Some(if origin == "*" {
+            AllowedOrigins::Any
+        } else {
+            AllowedOrigins::Exact(HashSet::from([origin]))
+        });
+        self
+    }
+
+    pub fn allow_origins(mut self, origins: Vec<String>) -> Self {
+        self.config.allow_origin = Some(AllowedOrigins::Exact(origins.into_iter().collect()));
+        self
+    }
+
+    /// Allows any origin matching one of `patterns`. Each pattern is
+    /// anchored to match the *entire* origin (as if wrapped in `^(?:...)$`)
+    /// and compiled as a regular expression in `build()`, which reports a
+    /// `CorsConfigError::InvalidOriginPattern` if any pattern is invalid.
+    /// Anchoring is automatic so an unanchored pattern can't be satisfied
+    /// by a substring of an attacker-controlled origin.
+    pub fn allow_origin_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.origin_patterns = Some(patterns);
         self
     }
 
@@ -173,24 +341,75 @@ impl CorsConfigBuilder {
         self
     }
 
-    pub fn build(self) -> CorsConfig {
-        self.config
+    /// Adds extra `Vary` header values to merge with the ones the CORS
+    /// layer derives automatically, rather than overwriting them.
+    pub fn vary(mut self, vary: Vec<String>) -> Self {
+        self.config.vary = Some(vary);
+        self
+    }
+
+    /// Validates the configured options and builds the `CorsConfig`.
+    ///
+    /// Returns `Err` when `allow_credentials` is combined with a wildcard
+    /// `allow_origin`, `allow_headers`, or `allow_methods` (browsers ignore
+    /// `Access-Control-Allow-Credentials` in that case), or when an
+    /// `allow_origin_patterns` pattern fails to compile.
+    pub fn build(mut self) -> Result<CorsConfig, CorsConfigError> {
+        if let Some(patterns) = self.origin_patterns.take() {
+            let mut compiled = Vec::with_capacity(patterns.len());
+            for pattern in patterns {
+                // Anchor to the whole origin: `is_match` is an unanchored
+                // substring search, so an unanchored pattern would let
+                // `https://evil.example.com.attacker.net` match a pattern
+                // meant to allow only subdomains of `example.com`.
+                let anchored = format!("^(?:{pattern})$");
+                let regex = Regex::new(&anchored)
+                    .map_err(|_| CorsConfigError::InvalidOriginPattern(pattern))?;
+                compiled.push(regex);
+            }
+            self.config.allow_origin = Some(AllowedOrigins::Patterns(compiled));
+        }
+
+        if self.config.allow_credentials {
+            if matches!(self.config.allow_origin, Some(AllowedOrigins::Any)) {
+                return Err(CorsConfigError::CredentialsWithWildcardOrigin);
+            }
+
+            if is_wildcard(&self.config.allow_headers) {
+                return Err(CorsConfigError::CredentialsWithWildcardHeaders);
+            }
+
+            if is_wildcard(&self.config.allow_methods) {
+                return Err(CorsConfigError::CredentialsWithWildcardMethods);
+            }
+        }
+
+        Ok(self.config)
     }
 }
 
+fn is_wildcard(values: &Option<Vec<String>>) -> bool {
+    values
+        .as_ref()
+        .is_some_and(|values| values.iter().any(|value| value == "*"))
+}
+
 /// CORS configuration definition for server configuration file.
 /// This struct maps the values from the server configuration file
 /// to a `CorsConfig` struct
-#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct CorsConfigFile {
     pub allow_credentials: bool,
     pub allow_headers: Option<Vec<String>>,
     pub allow_methods: Option<Vec<String>>,
     pub allow_origin: Option<String>,
+    pub allow_origins: Option<Vec<String>>,
+    pub allow_origin_patterns: Option<Vec<String>>,
     pub expose_headers: Option<Vec<String>>,
     pub max_age: Option<f64>,
     pub request_headers: Option<Vec<String>>,
     pub request_method: Option<String>,
+    pub vary: Option<Vec<String>>,
 }
 
 impl TryFrom<CorsConfigFile> for CorsConfig {
@@ -215,6 +434,14 @@ impl TryFrom<CorsConfigFile> for CorsConfig {
             cors_config_builder = cors_config_builder.allow_origin(allow_origin);
         }
 
+        if let Some(allow_origins) = file_config.allow_origins {
+            cors_config_builder = cors_config_builder.allow_origins(allow_origins);
+        }
+
+        if let Some(allow_origin_patterns) = file_config.allow_origin_patterns {
+            cors_config_builder = cors_config_builder.allow_origin_patterns(allow_origin_patterns);
+        }
+
         if let Some(expose_headers) = file_config.expose_headers {
             cors_config_builder = cors_config_builder.expose_headers(expose_headers);
         }
@@ -231,7 +458,145 @@ impl TryFrom<CorsConfigFile> for CorsConfig {
             cors_config_builder = cors_config_builder.request_method(request_method);
         }
 
-        Ok(cors_config_builder.build())
+        if let Some(vary) = file_config.vary {
+            cors_config_builder = cors_config_builder.vary(vary);
+        }
+
+        cors_config_builder
+            .build()
+            .map_err(|err| Error::msg(err.to_string()))
+    }
+}
+
+/// Applies a [`CorsConfig`] to an individual request/response exchange.
+///
+/// `CorsLayer` holds no state of its own beyond the `CorsConfig` it wraps;
+/// call `apply` once per request with that request's method and headers.
+/// Preflight (`OPTIONS`) requests are short-circuited with a `204 No
+/// Content` response carrying the allowed methods/headers; actual requests
+/// get the `Access-Control-Allow-*` response headers appended in place.
+pub struct CorsLayer<'a> {
+    config: &'a CorsConfig,
+}
+
+impl<'a> CorsLayer<'a> {
+    pub fn new(config: &'a CorsConfig) -> Self {
+        CorsLayer { config }
+    }
+
+    /// Applies CORS handling for a request with the given `method` and
+    /// `request_headers`, writing response headers into `response_headers`.
+    ///
+    /// Returns `Some(StatusCode::NO_CONTENT)` when the request is a CORS
+    /// preflight request; the caller should short-circuit and respond with
+    /// that status and the headers already written into `response_headers`.
+    /// Returns `None` for every other request, including ones with no
+    /// `Origin` header at all (nothing to do) and ones whose origin is not
+    /// allowed (no CORS headers are added, letting the browser block it).
+    pub fn apply(
+        &self,
+        method: &Method,
+        request_headers: &HeaderMap,
+        response_headers: &mut HeaderMap,
+    ) -> Option<StatusCode> {
+        let origin = request_headers.get(http::header::ORIGIN)?.to_str().ok()?;
+        let allowed_origin = self.config.resolve_allow_origin(origin)?;
+        let mut vary = vec!["Origin"];
+
+        set_header(
+            response_headers,
+            "Access-Control-Allow-Origin",
+            &allowed_origin,
+        );
+
+        if self.config.allow_credentials {
+            set_header(response_headers, "Access-Control-Allow-Credentials", "true");
+        }
+
+        let is_preflight = *method == Method::OPTIONS
+            && request_headers.contains_key("Access-Control-Request-Method");
+
+        if is_preflight {
+            vary.push("Access-Control-Request-Method");
+            if let Some(allow_methods) = self.preflight_allow_methods(request_headers) {
+                set_header(response_headers, "Access-Control-Allow-Methods", &allow_methods);
+            }
+
+            vary.push("Access-Control-Request-Headers");
+            if let Some(allow_headers) = self.preflight_allow_headers(request_headers) {
+                set_header(response_headers, "Access-Control-Allow-Headers", &allow_headers);
+            }
+
+            if let Some(max_age) = self.config.max_age {
+                set_header(
+                    response_headers,
+                    "Access-Control-Max-Age",
+                    &max_age.as_secs().to_string(),
+                );
+            }
+
+            self.set_vary(response_headers, &vary);
+            return Some(StatusCode::NO_CONTENT);
+        }
+
+        if let Some(expose_headers) = &self.config.expose_headers {
+            set_header(
+                response_headers,
+                "Access-Control-Expose-Headers",
+                &expose_headers.join(", "),
+            );
+        }
+
+        self.set_vary(response_headers, &vary);
+        None
+    }
+
+    fn preflight_allow_methods(&self, request_headers: &HeaderMap) -> Option<String> {
+        if self.config.very_permissive {
+            return request_headers
+                .get("Access-Control-Request-Method")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+        }
+
+        self.config.allow_methods.as_ref().map(|methods| methods.join(", "))
+    }
+
+    fn preflight_allow_headers(&self, request_headers: &HeaderMap) -> Option<String> {
+        if self.config.very_permissive {
+            return request_headers
+                .get("Access-Control-Request-Headers")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from);
+        }
+
+        self.config.allow_headers.as_ref().map(|headers| headers.join(", "))
+    }
+
+    /// Sets the `Vary` response header to `derived` (the entries the CORS
+    /// layer determined automatically for this request) merged with any
+    /// user-configured `CorsConfig::vary` entries, deduplicated.
+    fn set_vary(&self, response_headers: &mut HeaderMap, derived: &[&str]) {
+        let mut merged: Vec<String> = derived.iter().map(|entry| entry.to_string()).collect();
+
+        if let Some(extra) = &self.config.vary {
+            for entry in extra {
+                if !merged.iter().any(|existing| existing.eq_ignore_ascii_case(entry)) {
+                    merged.push(entry.clone());
+                }
+            }
+        }
+
+        set_header(response_headers, "Vary", &merged.join(", "));
+    }
+}
+
+fn set_header(headers: &mut HeaderMap, name: &str, value: &str) {
+    if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(name.as_bytes()),
+        HeaderValue::from_str(value),
+    ) {
+        headers.insert(name, value);
     }
 }
 
@@ -253,11 +618,14 @@ mod tests {
                 "Origin".to_string(),
                 "Content-Length".to_string(),
             ])
-            .build();
+            .build()
+            .unwrap();
 
         assert_eq!(
             cors_config.allow_origin,
-            Some(String::from("http://example.com"))
+            Some(AllowedOrigins::Exact(HashSet::from([String::from(
+                "http://example.com"
+            )])))
         );
         assert_eq!(
             cors_config.allow_methods,
@@ -287,7 +655,7 @@ mod tests {
     fn creates_cors_config_which_allows_all_connections() {
         let cors_config = CorsConfig::allow_all();
 
-        assert_eq!(cors_config.allow_origin, Some(String::from("*")));
+        assert_eq!(cors_config.allow_origin, Some(AllowedOrigins::Any));
         assert_eq!(
             cors_config.allow_methods,
             Some(vec![
@@ -314,6 +682,18 @@ mod tests {
         assert_eq!(cors_config.request_method, None);
     }
 
+    #[test]
+    fn creates_cors_config_which_is_very_permissive() {
+        let cors_config = CorsConfig::very_permissive();
+
+        assert_eq!(cors_config.allow_origin, Some(AllowedOrigins::Reflect));
+        assert_eq!(cors_config.allow_methods, None);
+        assert_eq!(cors_config.allow_headers, None);
+        assert_eq!(cors_config.allow_credentials, true);
+        assert_eq!(cors_config.very_permissive, true);
+        assert_eq!(cors_config.max_age, Some(Duration::from_secs(43200)));
+    }
+
     #[test]
     fn creates_cors_config_from_file() {
         let allow_headers = vec![
@@ -336,22 +716,191 @@ mod tests {
             allow_headers: Some(allow_headers.clone()),
             allow_methods: Some(allow_mehtods.clone()),
             allow_origin: Some(allow_origin.clone()),
+            allow_origins: None,
+            allow_origin_patterns: None,
             expose_headers: Some(expose_headers.clone()),
             max_age: Some(max_age),
             request_headers: Some(request_headers.clone()),
             request_method: Some(request_method.clone()),
+            vary: None,
         };
         let cors_config = CorsConfig {
             allow_credentials: true,
             allow_headers: Some(allow_headers),
             allow_methods: Some(allow_mehtods),
-            allow_origin: Some(allow_origin),
+            allow_origin: Some(AllowedOrigins::Exact(HashSet::from([allow_origin]))),
             expose_headers: Some(expose_headers),
             max_age: Some(Duration::from_secs_f64(max_age)),
             request_headers: Some(request_headers),
             request_method: Some(request_method),
+            very_permissive: false,
+            vary: None,
         };
 
         assert_eq!(cors_config, CorsConfig::try_from(file_config).unwrap());
     }
+
+    #[test]
+    fn rejects_credentials_with_wildcard_origin() {
+        let err = CorsConfig::builder()
+            .allow_origin("*".to_string())
+            .allow_credentials()
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err, CorsConfigError::CredentialsWithWildcardOrigin);
+    }
+
+    #[test]
+    fn rejects_credentials_with_wildcard_headers() {
+        let err = CorsConfig::builder()
+            .allow_origin("http://example.com".to_string())
+            .allow_headers(vec!["*".to_string()])
+            .allow_credentials()
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err, CorsConfigError::CredentialsWithWildcardHeaders);
+    }
+
+    #[test]
+    fn rejects_invalid_origin_pattern() {
+        let err = CorsConfig::builder()
+            .allow_origin_patterns(vec!["(".to_string()])
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err, CorsConfigError::InvalidOriginPattern("(".to_string()));
+    }
+
+    #[test]
+    fn origin_patterns_are_anchored_against_substring_bypass() {
+        let config = CorsConfig::builder()
+            .allow_origin_patterns(vec![r"https://([a-z0-9-]+\.)?example\.com".to_string()])
+            .build()
+            .unwrap();
+        let Some(AllowedOrigins::Patterns(patterns)) = &config.allow_origin else {
+            panic!("expected AllowedOrigins::Patterns");
+        };
+
+        assert!(patterns[0].is_match("https://example.com"));
+        assert!(patterns[0].is_match("https://api.example.com"));
+        assert!(!patterns[0].is_match("https://evil.example.com.attacker.net"));
+        assert!(!patterns[0].is_match("https://attacker.net/https://example.com"));
+    }
+
+    #[test]
+    fn applies_allowed_origin_to_actual_request() {
+        let config = CorsConfig::builder()
+            .allow_origin("http://example.com".to_string())
+            .allow_credentials()
+            .build()
+            .unwrap();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("Origin", "http://example.com".parse().unwrap());
+        let mut response_headers = HeaderMap::new();
+
+        let status = CorsLayer::new(&config).apply(&Method::GET, &request_headers, &mut response_headers);
+
+        assert_eq!(status, None);
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Origin").unwrap(),
+            "http://example.com"
+        );
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Credentials").unwrap(),
+            "true"
+        );
+        assert_eq!(response_headers.get("Vary").unwrap(), "Origin");
+    }
+
+    #[test]
+    fn disallowed_origin_gets_no_cors_headers() {
+        let config = CorsConfig::builder()
+            .allow_origin("http://example.com".to_string())
+            .build()
+            .unwrap();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("Origin", "http://evil.example".parse().unwrap());
+        let mut response_headers = HeaderMap::new();
+
+        let status = CorsLayer::new(&config).apply(&Method::GET, &request_headers, &mut response_headers);
+
+        assert_eq!(status, None);
+        assert!(response_headers.is_empty());
+    }
+
+    #[test]
+    fn short_circuits_preflight_request() {
+        let config = CorsConfig::allow_all();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("Origin", "http://example.com".parse().unwrap());
+        request_headers.insert("Access-Control-Request-Method", "PUT".parse().unwrap());
+        let mut response_headers = HeaderMap::new();
+
+        let status =
+            CorsLayer::new(&config).apply(&Method::OPTIONS, &request_headers, &mut response_headers);
+
+        assert_eq!(status, Some(StatusCode::NO_CONTENT));
+        assert_eq!(response_headers.get("Access-Control-Allow-Origin").unwrap(), "*");
+        assert!(response_headers.contains_key("Access-Control-Allow-Methods"));
+        assert!(response_headers.contains_key("Access-Control-Max-Age"));
+        assert_eq!(
+            response_headers.get("Vary").unwrap(),
+            "Origin, Access-Control-Request-Method, Access-Control-Request-Headers"
+        );
+    }
+
+    #[test]
+    fn very_permissive_reflects_preflight_request_values() {
+        let config = CorsConfig::very_permissive();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("Origin", "http://example.com".parse().unwrap());
+        request_headers.insert("Access-Control-Request-Method", "DELETE".parse().unwrap());
+        request_headers.insert(
+            "Access-Control-Request-Headers",
+            "X-Custom-Header".parse().unwrap(),
+        );
+        let mut response_headers = HeaderMap::new();
+
+        let status =
+            CorsLayer::new(&config).apply(&Method::OPTIONS, &request_headers, &mut response_headers);
+
+        assert_eq!(status, Some(StatusCode::NO_CONTENT));
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Origin").unwrap(),
+            "http://example.com"
+        );
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Methods").unwrap(),
+            "DELETE"
+        );
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Headers").unwrap(),
+            "X-Custom-Header"
+        );
+        assert_eq!(
+            response_headers.get("Access-Control-Allow-Credentials").unwrap(),
+            "true"
+        );
+    }
+
+    #[test]
+    fn merges_configured_vary_with_derived_entries() {
+        let config = CorsConfig::builder()
+            .allow_origin("http://example.com".to_string())
+            .vary(vec!["Accept-Encoding".to_string()])
+            .build()
+            .unwrap();
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert("Origin", "http://example.com".parse().unwrap());
+        let mut response_headers = HeaderMap::new();
+
+        CorsLayer::new(&config).apply(&Method::GET, &request_headers, &mut response_headers);
+
+        assert_eq!(
+            response_headers.get("Vary").unwrap(),
+            "Origin, Accept-Encoding"
+        );
+    }
 }
\ No newline at end of file
@@ -1,12 +1,22 @@
 use anyhow::{Error, Result};
 use serde::Deserialize;
+use std::convert::TryFrom;
+use std::env;
 use std::fs;
 use std::net::IpAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use super::cors::{CorsConfig, CorsConfigFile};
 use super::tls::TlsConfigFile;
 
+/// Environment variable that, if set, overrides `ConfigFile::host`.
+const ENV_HOST: &str = "HTTP_SERVER_HOST";
+/// Environment variable that, if set, overrides `ConfigFile::port`.
+const ENV_PORT: &str = "HTTP_SERVER_PORT";
+/// Environment variable that, if set, overrides the `[cors]` `allow_origin`.
+const ENV_CORS_ALLOW_ORIGIN: &str = "HTTP_SERVER_CORS_ALLOW_ORIGIN";
+
 #[derive(Debug, Deserialize)]
 pub struct ConfigFile {
     pub host: IpAddr,
@@ -14,6 +24,7 @@ pub struct ConfigFile {
     pub verbose: bool,
     pub root_dir: Option<PathBuf>,
     pub tls: Option<TlsConfigFile>,
+    pub cors: Option<CorsConfigFile>,
 }
 
 impl ConfigFile {
@@ -25,7 +36,8 @@ impl ConfigFile {
         };
 
         let file = fs::read_to_string(file_path)?;
-        let config = ConfigFile::parse_toml(file.as_str())?;
+        let config = ConfigFile::parse_toml(file.as_str())?.apply_env_overrides();
+        config.validate_cors()?;
 
         Ok(config)
     }
@@ -39,6 +51,44 @@ impl ConfigFile {
             ))),
         }
     }
+
+    /// Layers environment variables on top of the values parsed from the
+    /// config file, so the same binary can be reconfigured per-deployment
+    /// without editing the TOML.
+    fn apply_env_overrides(mut self) -> Self {
+        if let Some(host) = env::var(ENV_HOST).ok().and_then(|host| IpAddr::from_str(&host).ok()) {
+            self.host = host;
+        }
+
+        if let Some(port) = env::var(ENV_PORT).ok().and_then(|port| port.parse().ok()) {
+            self.port = port;
+        }
+
+        if let Ok(allow_origin) = env::var(ENV_CORS_ALLOW_ORIGIN) {
+            let mut cors = self.cors.take().unwrap_or_default();
+            // `TryFrom<CorsConfigFile>` applies allow_origin, then
+            // allow_origins, then allow_origin_patterns onto the same
+            // CorsConfig field, so clear the other two or the file's
+            // values would silently win over this override.
+            cors.allow_origin = Some(allow_origin);
+            cors.allow_origins = None;
+            cors.allow_origin_patterns = None;
+            self.cors = Some(cors);
+        }
+
+        self
+    }
+
+    /// Validates the `[cors]` section, if present, by running it through
+    /// `CorsConfig`'s existing validation so a bad configuration fails
+    /// loudly at parse time rather than at request time.
+    fn validate_cors(&self) -> Result<()> {
+        if let Some(cors) = &self.cors {
+            CorsConfig::try_from(cors.clone())?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -138,4 +188,104 @@ mod tests {
         assert_eq!(config.tls.unwrap(), tls);
         assert!(config.verbose);
     }
+
+    #[test]
+    fn parses_config_with_cors() {
+        let file_contents = r#"
+            host = "192.168.0.1"
+            port = 7878
+            verbose = true
+            root_dir = "~/Desktop"
+
+            [cors]
+            allow_credentials = false
+            allow_origin = "https://example.com"
+        "#;
+        let config = ConfigFile::parse_toml(file_contents).unwrap();
+        let cors = config.cors.unwrap();
+
+        assert_eq!(cors.allow_origin, Some(String::from("https://example.com")));
+        assert!(!cors.allow_credentials);
+    }
+
+    #[test]
+    fn rejects_invalid_cors_section() {
+        let file_contents = r#"
+            host = "192.168.0.1"
+            port = 7878
+            verbose = true
+            root_dir = "~/Desktop"
+
+            [cors]
+            allow_credentials = true
+            allow_origin = "*"
+        "#;
+        let config = ConfigFile::parse_toml(file_contents).unwrap();
+
+        assert!(config.validate_cors().is_err());
+    }
+
+    #[test]
+    fn env_vars_override_file_values() {
+        // SAFETY: this test owns ENV_HOST/ENV_PORT for its duration and
+        // clears them before returning; no other test sets these vars, and
+        // the one that reads them via apply_env_overrides asserts only on cors.
+        unsafe {
+            env::set_var(ENV_HOST, "10.0.0.5");
+            env::set_var(ENV_PORT, "9000");
+        }
+
+        let file_contents = r#"
+            host = "192.168.0.1"
+            port = 7878
+            verbose = true
+            root_dir = "~/Desktop"
+        "#;
+        let config = ConfigFile::parse_toml(file_contents)
+            .unwrap()
+            .apply_env_overrides();
+
+        assert_eq!(config.host, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(config.port, 9000);
+
+        unsafe {
+            env::remove_var(ENV_HOST);
+            env::remove_var(ENV_PORT);
+        }
+    }
+
+    #[test]
+    fn env_cors_allow_origin_overrides_file_allow_origins() {
+        // SAFETY: this test owns ENV_CORS_ALLOW_ORIGIN for its duration and
+        // clears it before returning; no other test sets this var, and the
+        // one that reads it via apply_env_overrides asserts only on host/port.
+        unsafe {
+            env::set_var(ENV_CORS_ALLOW_ORIGIN, "https://b.com");
+        }
+
+        let file_contents = r#"
+            host = "192.168.0.1"
+            port = 7878
+            verbose = true
+            root_dir = "~/Desktop"
+
+            [cors]
+            allow_credentials = false
+            allow_origins = ["https://a.com"]
+        "#;
+        let config = ConfigFile::parse_toml(file_contents)
+            .unwrap()
+            .apply_env_overrides();
+        let cors_config = CorsConfig::try_from(config.cors.unwrap()).unwrap();
+
+        assert_eq!(
+            cors_config.resolve_allow_origin("https://b.com"),
+            Some(String::from("https://b.com"))
+        );
+        assert_eq!(cors_config.resolve_allow_origin("https://a.com"), None);
+
+        unsafe {
+            env::remove_var(ENV_CORS_ALLOW_ORIGIN);
+        }
+    }
 }